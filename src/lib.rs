@@ -1,16 +1,22 @@
 extern crate pyo3;
 
+mod mcts;
+
 use goban::pieces::goban::Goban;
 use goban::pieces::stones::{Color, Stone};
 use goban::pieces::util::coord::{Point, Order};
-use goban::rules::{GobanSizes, Move};
+use goban::rules::{GobanSizes, IllegalRules, Move};
 use goban::rules::Player;
 use goban::rules::Rule;
 use pyo3::prelude::*;
 use goban::rules::Player::{White, Black};
 use std::ops::Deref;
 use goban::rules::game::Game;
+use goban::rules::sgf_bridge;
 use pyo3::exceptions;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
 
 #[inline]
 fn to_color(b: bool) -> Color {
@@ -39,6 +45,110 @@ fn vec_color_to_raw_split(vec: Vec<Color>) -> (Vec<bool>, Vec<bool>) {
     vec.into_iter().map(to_bit_tuple).unzip()
 }
 
+/// True if `point` is a valid coordinate on `goban`.
+fn point_in_bounds(goban: &Goban, point: Point) -> bool {
+    let (height, width) = goban.size();
+    point.0 < width && point.1 < height
+}
+
+/// Place `color` at `point` on a copy of `goban`'s stones, removing any
+/// neighboring enemy groups left with no liberties. Only touches the board,
+/// not the rest of `Game` (prisoners, rule, hash history), which a one-ply
+/// score estimate doesn't need. Returns the new board and how many enemy
+/// stones were captured by the move. `point` must already be in bounds —
+/// callers only ever pass points from `Game::legals()`.
+fn play_on_goban(goban: &Goban, point: Point, color: Color) -> (Goban, usize) {
+    debug_assert!(
+        point_in_bounds(goban, point),
+        "play_on_goban: point out of bounds for this goban"
+    );
+    let (_, width) = goban.size();
+    let index = |p: Point| p.1 as usize * width as usize + p.0 as usize;
+
+    let mut cells = goban.raw();
+    cells[index(point)] = color;
+    let mut next = Goban::from_array(&cells, Order::RowMajor);
+
+    let opponent = match color {
+        Color::Black => Color::White,
+        Color::White => Color::Black,
+        Color::None => return (next, 0),
+    };
+
+    let mut captured = Vec::new();
+    let mut checked = HashSet::new();
+    for stone in next.get_neighbors(point) {
+        if stone.color == opponent && checked.insert(stone.coordinates) {
+            let (group, liberties) = group_and_liberties(&next, stone.coordinates);
+            if liberties.is_empty() {
+                checked.extend(group.iter().copied());
+                captured.extend(group);
+            }
+        }
+    }
+    let captured_count = captured.len();
+    if !captured.is_empty() {
+        let mut cells = next.raw();
+        for p in captured {
+            cells[index(p)] = Color::None;
+        }
+        next = Goban::from_array(&cells, Order::RowMajor);
+    }
+    (next, captured_count)
+}
+
+/// Score a standalone board the way `rule` would: area scoring (stones +
+/// territory) for Chinese/Tromp-Taylor, territory scoring (territory +
+/// prisoners, stones on the board don't count) for Japanese. `prisoners`
+/// must already include any stones captured by the move being evaluated.
+/// Komi is added to White either way.
+fn score_from_goban(goban: &Goban, rule: Rule, komi: f32, prisoners: (u32, u32)) -> (f32, f32) {
+    let (black_territory, white_territory) = goban.calculate_territories();
+    match rule {
+        Rule::Japanese => {
+            let (black_prisoners, white_prisoners) = prisoners;
+            (
+                black_territory as f32 + black_prisoners as f32,
+                white_territory as f32 + white_prisoners as f32 + komi,
+            )
+        }
+        Rule::Chinese | Rule::TrompTaylor => {
+            let (black_stones, white_stones) =
+                goban
+                    .raw()
+                    .into_iter()
+                    .fold((0u32, 0u32), |(b, w), color| match color {
+                        Color::Black => (b + 1, w),
+                        Color::White => (b, w + 1),
+                        Color::None => (b, w),
+                    });
+            (
+                black_stones as f32 + black_territory as f32,
+                white_stones as f32 + white_territory as f32 + komi,
+            )
+        }
+    }
+}
+
+fn parse_rule(rule: &str) -> PyResult<Rule> {
+    match rule {
+        "chinese" => Ok(Rule::Chinese),
+        "japanese" => Ok(Rule::Japanese),
+        "tromp_taylor" => Ok(Rule::TrompTaylor),
+        _ => Err(exceptions::ValueError::py_err(
+            "rule must be one of \"chinese\", \"japanese\", \"tromp_taylor\"",
+        )),
+    }
+}
+
+fn rule_name(rule: Rule) -> &'static str {
+    match rule {
+        Rule::Chinese => "chinese",
+        Rule::Japanese => "japanese",
+        Rule::TrompTaylor => "tromp_taylor",
+    }
+}
+
 #[pymodule]
 pub fn libgoban(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<PyGoban>()?;
@@ -76,6 +186,50 @@ impl From<&Goban> for PyGoban {
     }
 }
 
+/// Flood-fill the connected same-color chain starting at `point`, returning
+/// it alongside its liberties. `point` must already hold a stone.
+fn group_and_liberties(goban: &Goban, point: Point) -> (Vec<Point>, Vec<Point>) {
+    let color = goban.get_stone(point);
+    let mut group = Vec::new();
+    let mut liberties = Vec::new();
+    let mut seen_group = HashSet::new();
+    let mut seen_liberties = HashSet::new();
+    let mut stack = vec![point];
+    seen_group.insert(point);
+
+    while let Some(p) = stack.pop() {
+        group.push(p);
+        for stone in goban.get_neighbors(p) {
+            if stone.color == color {
+                if seen_group.insert(stone.coordinates) {
+                    stack.push(stone.coordinates);
+                }
+            } else if stone.color == Color::None && seen_liberties.insert(stone.coordinates) {
+                liberties.push(stone.coordinates);
+            }
+        }
+    }
+    (group, liberties)
+}
+
+impl PyGoban {
+    /// `group_and_liberties` guarded against empty points, which have no
+    /// group or liberties of their own to report.
+    fn group_and_liberties_at(&self, point: Point) -> PyResult<(Vec<Point>, Vec<Point>)> {
+        if !point_in_bounds(&self.goban, point) {
+            return Err(exceptions::ValueError::py_err(
+                "point is out of bounds for this goban",
+            ));
+        }
+        if self.goban.get_stone(point) == Color::None {
+            return Err(exceptions::ValueError::py_err(
+                "point is empty: group/liberty queries need a stone to start from",
+            ));
+        }
+        Ok(group_and_liberties(&self.goban, point))
+    }
+}
+
 #[pymethods]
 impl PyGoban {
     #[new]
@@ -97,19 +251,124 @@ impl PyGoban {
     pub fn pretty_string(&self) -> PyResult<String> {
         Ok(self.goban.pretty_string())
     }
+
+    /// The connected chain of same-color stones that `point` belongs to.
+    pub fn group(&self, point: Point) -> PyResult<Vec<Point>> {
+        Ok(self.group_and_liberties_at(point)?.0)
+    }
+
+    /// The empty points adjacent to the group `point` belongs to.
+    pub fn liberties(&self, point: Point) -> PyResult<Vec<Point>> {
+        Ok(self.group_and_liberties_at(point)?.1)
+    }
+
+    /// True if the group `point` belongs to has no liberties left.
+    pub fn is_captured(&self, point: Point) -> PyResult<bool> {
+        Ok(self.group_and_liberties_at(point)?.1.is_empty())
+    }
+
+    /// Number of liberties of the group `point` belongs to.
+    pub fn count_liberties(&self, point: Point) -> PyResult<usize> {
+        Ok(self.group_and_liberties_at(point)?.1.len())
+    }
+}
+
+/// The stable on-the-wire representation of a full game used by
+/// `PyGame.to_json`/`PyGame.from_json`. Unlike `raw()`, it keeps komi, rule,
+/// prisoners and history, so a game whose position comes from its plays and
+/// passes alone can be stored and rebuilt exactly.
+#[derive(Serialize, Deserialize)]
+struct GameStateJson {
+    size: (u8, u8),
+    stones: Vec<u8>,
+    komi: f32,
+    rule: String,
+    prisoners: (u32, u32),
+    turn: bool,
+    passes: u8,
+    history: Vec<Option<Point>>,
+}
+
+/// A node of the move tree, holding the move that led to it and a cached
+/// snapshot of the game at that point so navigation never replays moves.
+/// Resignation is never tracked here: it ends the game rather than
+/// advancing a board position, so it stays out of the reviewable tree.
+#[derive(Clone, Debug)]
+struct MoveNode {
+    mov: Option<Move>,
+    game: Game,
+    parent: Option<usize>,
+}
+
+impl MoveNode {
+    fn root(game: Game) -> Self {
+        MoveNode {
+            mov: None,
+            game,
+            parent: None,
+        }
+    }
 }
 
 #[pyclass(name = Game)]
 #[derive(Clone, Debug)]
 pub struct PyGame {
     game: Game,
+    tree: Vec<MoveNode>,
+    current: usize,
+}
+
+impl PyGame {
+    fn from_game(game: Game) -> Self {
+        PyGame {
+            tree: vec![MoveNode::root(game.clone())],
+            game,
+            current: 0,
+        }
+    }
+
+    /// Push a new child of the current node and move onto it.
+    fn push_move(&mut self, mov: Move) {
+        let index = self.tree.len();
+        self.tree.push(MoveNode {
+            mov: Some(mov),
+            game: self.game.clone(),
+            parent: Some(self.current),
+        });
+        self.current = index;
+    }
+
+    /// Score a hypothetical move by mutating a throwaway copy of just the
+    /// board, not a whole `Game` (whose prisoner counts and hash history
+    /// would make cloning ~361 candidates per call needlessly expensive).
+    fn try_move(&self, point: Point) -> (f32, f32) {
+        let color = match self.game.turn() {
+            Player::White => Color::White,
+            Player::Black => Color::Black,
+        };
+        let (goban, captured) = play_on_goban(self.game.goban(), point, color);
+        let (mut black_prisoners, mut white_prisoners) = self.game.prisoners();
+        match color {
+            Color::Black => black_prisoners += captured as u32,
+            Color::White => white_prisoners += captured as u32,
+            Color::None => {}
+        }
+        score_from_goban(
+            &goban,
+            self.game.rule(),
+            self.game.komi(),
+            (black_prisoners, white_prisoners),
+        )
+    }
 }
 
 #[pymethods]
 impl PyGame {
     #[new]
-    /// By default the rule are chinese
-    pub fn new(size: usize) -> Self {
+    #[args(rule = "\"chinese\"")]
+    /// By default the rule are chinese. `rule` accepts "chinese", "japanese"
+    /// or "tromp_taylor".
+    pub fn new(size: usize, rule: &str) -> PyResult<Self> {
         let s = match size {
             9 => GobanSizes::Nine,
             13 => GobanSizes::Thirteen,
@@ -117,9 +376,7 @@ impl PyGame {
             _ => panic!("You must choose 9, 13, 19"),
         };
 
-        PyGame {
-            game: Game::new(s, Rule::Chinese)
-        }
+        Ok(PyGame::from_game(Game::new(s, parse_rule(rule)?)))
     }
 
     pub fn put_handicap(&mut self, coords: Vec<Point>) -> PyResult<()> {
@@ -212,14 +469,79 @@ impl PyGame {
 
     /// Play the move in the go game, pass None to Pass
     /// Don't check if the play is legal.
+    /// Appends a child to the current node of the move tree.
     pub fn play(&mut self, play: Option<Point>) -> PyResult<()> {
-        match play {
-            Some(mov) => self.game.play(Move::Play(mov.0, mov.1)),
-            None => self.game.play(Move::Pass),
+        let mov = match play {
+            Some(point) => Move::Play(point.0, point.1),
+            None => Move::Pass,
         };
+        self.game.play(mov);
+        self.push_move(mov);
         Ok(())
     }
 
+    /// Undo the last move, moving back to the parent node.
+    /// Does nothing if already at the root.
+    pub fn undo(&mut self) -> PyResult<()> {
+        if let Some(parent) = self.tree[self.current].parent {
+            self.current = parent;
+            self.game = self.tree[self.current].game.clone();
+        }
+        Ok(())
+    }
+
+    /// Jump to any node of the move tree, rebuilding the game state.
+    pub fn goto(&mut self, move_index: usize) -> PyResult<()> {
+        match self.tree.get(move_index) {
+            Some(node) => {
+                self.current = move_index;
+                self.game = node.game.clone();
+                Ok(())
+            }
+            None => Err(exceptions::IndexError::py_err("move_index out of range")),
+        }
+    }
+
+    /// Index of the node the game is currently on.
+    pub fn current_move_index(&self) -> PyResult<usize> {
+        Ok(self.current)
+    }
+
+    /// The mainline of moves from the root to the current node. Every node
+    /// in the tree is a `Play` or a `Pass` (see `MoveNode`), so `None` here
+    /// unambiguously means a pass.
+    pub fn history(&self) -> PyResult<Vec<Option<Point>>> {
+        let mut moves = Vec::new();
+        let mut node = self.current;
+        while let Some(parent) = self.tree[node].parent {
+            moves.push(match self.tree[node].mov {
+                Some(Move::Play(x, y)) => Some((x, y)),
+                _ => None,
+            });
+            node = parent;
+        }
+        moves.reverse();
+        Ok(moves)
+    }
+
+    /// Fork the current node into a new variation, without moving the
+    /// mainline onto it. Returns the index of the new node, usable with `goto`.
+    pub fn branch(&mut self, play: Option<Point>) -> PyResult<usize> {
+        let mov = match play {
+            Some(point) => Move::Play(point.0, point.1),
+            None => Move::Pass,
+        };
+        let mut game = self.tree[self.current].game.clone();
+        game.play(mov);
+        let index = self.tree.len();
+        self.tree.push(MoveNode {
+            mov: Some(mov),
+            game,
+            parent: Some(self.current),
+        });
+        Ok(index)
+    }
+
     /// Play a move then return a clone
     pub fn play_and_clone(&self, play: Option<Point>) -> PyResult<Self> {
         let mut x = self.clone();
@@ -230,6 +552,8 @@ impl PyGame {
     /// Resign passing
     /// true resigns White
     /// false resigns Black
+    /// This ends the game but isn't a board move, so it doesn't enter the
+    /// move tree: `history()`/`undo()` still reflect the last position played.
     pub fn resign(&mut self, player: bool) -> PyResult<()> {
         self.game.play(Move::Resign(
             if player { White } else { Black }
@@ -242,11 +566,105 @@ impl PyGame {
         Ok(self.game.legals().collect())
     }
 
+    /// Legal moves under a chosen subset of the illegal-move checks, e.g.
+    /// allowing suicide while still forbidding ko.
+    pub fn legals_by(&self, suicide: bool, ko: bool, superko: bool) -> PyResult<Vec<Point>> {
+        Ok(self
+            .game
+            .legals_by(IllegalRules { suicide, ko, superko })
+            .collect())
+    }
+
     /// return true if the point is legal
     pub fn is_legal(&self, point: Point) -> PyResult<bool> {
         Ok(self.game.check_point(point).is_none())
     }
 
+    /// Get the current rule as a string ("chinese", "japanese" or "tromp_taylor")
+    pub fn get_rule(&self) -> PyResult<String> {
+        Ok(rule_name(self.game.rule()).to_string())
+    }
+
+    /// Set the rule used for legality checks and scoring
+    pub fn set_rule(&mut self, rule: &str) -> PyResult<()> {
+        self.game.set_rule(parse_rule(rule)?);
+        Ok(())
+    }
+
+    /// Hash of the last played position, usable to reason about superko from Python
+    pub fn last_hash(&self) -> PyResult<u64> {
+        Ok(self.game.last_hash())
+    }
+
+    /// Suggest a move by running `iterations` of UCT Monte-Carlo tree search.
+    /// Returns None (pass) if there is no legal move to play.
+    #[args(exploration = "1.41")]
+    pub fn best_move_mcts(&self, iterations: u32, exploration: f64) -> PyResult<Option<Point>> {
+        Ok(mcts::best_move_mcts(&self.game, iterations, exploration))
+    }
+
+    /// For each legal point, play it on a throwaway copy of the board and
+    /// return the resulting score differential (my_score - opponent_score)
+    /// under the game's rule, from the current player's perspective.
+    pub fn evaluate_moves(&self) -> PyResult<Vec<(Point, f32)>> {
+        let mover = self.game.turn();
+        Ok(self
+            .game
+            .legals()
+            .map(|point| {
+                let (black_score, white_score) = self.try_move(point);
+                let diff = match mover {
+                    Player::Black => black_score - white_score,
+                    Player::White => white_score - black_score,
+                };
+                (point, diff)
+            })
+            .collect())
+    }
+
+    /// Greedy one-ply baseline: the legal move maximizing the immediate
+    /// score differential, or None (pass) if there is no legal move.
+    pub fn best_move_greedy(&self) -> PyResult<Option<Point>> {
+        Ok(self
+            .evaluate_moves()?
+            .into_iter()
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+            .map(|(point, _)| point))
+    }
+
+    /// Enemy stones a hypothetical play of `color` at `point` would remove.
+    /// `color` must be the side to move: the engine always plays using its
+    /// own turn tracker, so a mismatched `color` can't be forced through.
+    pub fn captures_if_played(&self, point: Point, color: bool) -> PyResult<Vec<Point>> {
+        if color != self.turn() {
+            return Err(exceptions::ValueError::py_err(
+                "captures_if_played: color must match the side to move",
+            ));
+        }
+        let played_color = to_color(color);
+        let opponent = match played_color {
+            Color::Black => Color::White,
+            Color::White => Color::Black,
+            Color::None => Color::None,
+        };
+        let before = self.game.goban().clone();
+        let mut game = self.game.clone();
+        game.play(Move::Play(point.0, point.1));
+        let after = game.goban();
+
+        let (height, width) = before.size();
+        let mut removed = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                let p = (x, y);
+                if before.get_stone(p) == opponent && after.get_stone(p) != opponent {
+                    removed.push(p);
+                }
+            }
+        }
+        Ok(removed)
+    }
+
     /// return all the empty intersection of the board,
     pub fn pseudo_legals(&self) -> PyResult<Vec<Point>> {
         Ok(self.game.pseudo_legals().collect())
@@ -266,4 +684,103 @@ impl PyGame {
         self.game.display_goban();
         Ok(())
     }
+
+    /// Build a game from a SGF string (handicap, komi, moves and result
+    /// included), replaying each move so the result can be stepped through
+    /// with `undo`/`goto`/`history` like any other game.
+    #[staticmethod]
+    pub fn from_sgf(sgf: &str) -> PyResult<Self> {
+        let parsed = sgf_bridge::parse_sgf(sgf)
+            .map_err(|err| exceptions::ValueError::py_err(err.to_string()))?;
+
+        let (height, width) = parsed.goban().size();
+        let size = match (height, width) {
+            (9, 9) => GobanSizes::Nine,
+            (13, 13) => GobanSizes::Thirteen,
+            (19, 19) => GobanSizes::Nineteen,
+            _ => return Err(exceptions::ValueError::py_err("unsupported goban size")),
+        };
+
+        let mut game = Game::new(size, parsed.rule());
+        game.set_komi(parsed.komi());
+        game.put_handicap(parsed.handicap());
+        let mut py_game = PyGame::from_game(game);
+
+        for mov in parsed.moves() {
+            match *mov {
+                Move::Play(x, y) => py_game.play(Some((x, y)))?,
+                Move::Pass => py_game.play(None)?,
+                Move::Resign(player) => py_game.resign(player == White)?,
+            };
+        }
+
+        Ok(py_game)
+    }
+
+    /// Build a game from a SGF file (handicap, komi, moves and result included)
+    #[staticmethod]
+    pub fn from_sgf_file(path: &str) -> PyResult<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| exceptions::IOError::py_err(err.to_string()))?;
+        Self::from_sgf(&content)
+    }
+
+    /// Serialize the game to a SGF string
+    pub fn to_sgf(&self) -> PyResult<String> {
+        Ok(sgf_bridge::to_sgf(&self.game))
+    }
+
+    /// Serialize the full game state (stones, size, komi, rule, prisoners,
+    /// turn, pass count and move history) to a stable JSON string.
+    pub fn to_json(&self) -> PyResult<String> {
+        let state = GameStateJson {
+            size: self.game.goban().size(),
+            stones: vec_color_to_u8(self.game.goban().raw()),
+            komi: self.game.komi(),
+            rule: rule_name(self.game.rule()).to_string(),
+            prisoners: self.game.prisoners(),
+            turn: self.turn(),
+            passes: self.game.passes(),
+            history: self.history()?,
+        };
+        serde_json::to_string(&state).map_err(|err| exceptions::ValueError::py_err(err.to_string()))
+    }
+
+    /// Rebuild a game from the JSON produced by `to_json`, by replaying its
+    /// move history on a fresh game of the same size, rule and komi.
+    ///
+    /// `history` only carries plays and passes (see `MoveNode`), so it can't
+    /// represent a resignation, and handicap stones are not replayed either.
+    /// The replayed state is checked against the serialized snapshot and
+    /// rejected on mismatch, so a game with handicap stones is rejected.
+    #[staticmethod]
+    pub fn from_json(text: &str) -> PyResult<Self> {
+        let state: GameStateJson = serde_json::from_str(text)
+            .map_err(|err| exceptions::ValueError::py_err(err.to_string()))?;
+        let size = match state.size {
+            (9, 9) => GobanSizes::Nine,
+            (13, 13) => GobanSizes::Thirteen,
+            (19, 19) => GobanSizes::Nineteen,
+            _ => return Err(exceptions::ValueError::py_err("unsupported goban size")),
+        };
+        let mut game = Game::new(size, parse_rule(&state.rule)?);
+        game.set_komi(state.komi);
+        let mut py_game = PyGame::from_game(game);
+        for mov in &state.history {
+            py_game.play(*mov)?;
+        }
+
+        if vec_color_to_u8(py_game.game.goban().raw()) != state.stones
+            || py_game.game.prisoners() != state.prisoners
+            || py_game.turn() != state.turn
+            || py_game.game.passes() != state.passes
+        {
+            return Err(exceptions::ValueError::py_err(
+                "from_json: replaying the move history didn't reproduce the serialized state \
+                 (e.g. a resignation can't be replayed from history alone)",
+            ));
+        }
+
+        Ok(py_game)
+    }
 }
@@ -0,0 +1,143 @@
+//! Plain UCT Monte-Carlo tree search over `Game`, used to back
+//! `PyGame.best_move_mcts` without reimplementing the rules through the
+//! Python binding (FFI round-trips per simulated move would be far too slow).
+
+use goban::pieces::util::coord::Point;
+use goban::rules::game::Game;
+use goban::rules::{Move, Player};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+struct Node {
+    game: Game,
+    mov: Option<Point>,
+    to_move: Player,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<Point>,
+    n: u32,
+    w: f64,
+}
+
+impl Node {
+    fn new(game: Game, mov: Option<Point>, parent: Option<usize>) -> Self {
+        Node {
+            to_move: game.turn(),
+            untried: game.legals().collect(),
+            game,
+            mov,
+            parent,
+            children: Vec::new(),
+            n: 0,
+            w: 0.0,
+        }
+    }
+}
+
+fn uct_value(child: &Node, parent_n: f64, exploration: f64) -> f64 {
+    if child.n == 0 {
+        return f64::INFINITY;
+    }
+    let n = f64::from(child.n);
+    child.w / n + exploration * (parent_n.ln() / n).sqrt()
+}
+
+fn select_child(nodes: &[Node], parent: usize, exploration: f64) -> usize {
+    let parent_n = f64::from(nodes[parent].n);
+    nodes[parent]
+        .children
+        .iter()
+        .copied()
+        .max_by(|&a, &b| {
+            uct_value(&nodes[a], parent_n, exploration)
+                .partial_cmp(&uct_value(&nodes[b], parent_n, exploration))
+                .unwrap()
+        })
+        .expect("selection only runs on nodes with children")
+}
+
+/// Play uniformly random legal moves from `game` until two consecutive
+/// passes (or no legal moves), then score the result.
+fn simulate(game: &Game) -> Player {
+    let mut game = game.clone();
+    let mut rng = rand::thread_rng();
+    let mut consecutive_passes = 0;
+    while consecutive_passes < 2 {
+        let legals: Vec<Point> = game.legals().collect();
+        match legals.choose(&mut rng) {
+            Some(&point) => {
+                game.play(Move::Play(point.0, point.1));
+                consecutive_passes = 0;
+            }
+            None => {
+                game.play(Move::Pass);
+                consecutive_passes += 1;
+            }
+        }
+    }
+    let (black_score, white_score) = game.calculate_score();
+    if black_score > white_score {
+        Player::Black
+    } else {
+        Player::White
+    }
+}
+
+fn backpropagate(nodes: &mut [Node], mut index: usize, winner: Player) {
+    loop {
+        nodes[index].n += 1;
+        // `to_move` is who is about to move *at* this node, i.e. the
+        // opponent of whoever played the move that produced it. Credit the
+        // win to the node when the mover who produced it won, so that
+        // `uct_value`/`select_child` (which reads a child's w/n from its
+        // parent) picks the child that was actually good for the parent.
+        if nodes[index].to_move != winner {
+            nodes[index].w += 1.0;
+        }
+        match nodes[index].parent {
+            Some(parent) => index = parent,
+            None => break,
+        }
+    }
+}
+
+/// Run `iterations` of UCT selection/expansion/simulation/backpropagation
+/// from `root_game`, then return the most-visited root child, or `None`
+/// (pass) if the position has no legal moves or `iterations` is 0.
+pub fn best_move_mcts(root_game: &Game, iterations: u32, exploration: f64) -> Option<Point> {
+    let mut nodes = vec![Node::new(root_game.clone(), None, None)];
+    if nodes[0].untried.is_empty() {
+        return None;
+    }
+
+    for _ in 0..iterations {
+        // Selection: descend while every untried move has been expanded.
+        let mut current = 0;
+        while nodes[current].untried.is_empty() && !nodes[current].children.is_empty() {
+            current = select_child(&nodes, current, exploration);
+        }
+
+        // Expansion: add one child for an untried move, if any remain.
+        let mut leaf = current;
+        if !nodes[current].untried.is_empty() {
+            let pick = rand::thread_rng().gen_range(0, nodes[current].untried.len());
+            let point = nodes[current].untried.remove(pick);
+            let mut game = nodes[current].game.clone();
+            game.play(Move::Play(point.0, point.1));
+            let index = nodes.len();
+            nodes.push(Node::new(game, Some(point), Some(current)));
+            nodes[current].children.push(index);
+            leaf = index;
+        }
+
+        // Simulation + backpropagation.
+        let winner = simulate(&nodes[leaf].game);
+        backpropagate(&mut nodes, leaf, winner);
+    }
+
+    nodes[0]
+        .children
+        .iter()
+        .max_by_key(|&&child| nodes[child].n)
+        .and_then(|&child| nodes[child].mov)
+}